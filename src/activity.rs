@@ -0,0 +1,67 @@
+//! The different things that can be logged against a day.
+
+use crate::units::{Distance, Weight, WorkoutDuration};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Activity {
+    /// a body weight measurement
+    Weight(Weight),
+    /// a step count
+    Steps(u32),
+    /// marks the day as a cheat day
+    CheatDay,
+    /// a timed activity with no tracked distance, e.g. the 7 minute workout
+    DurationWorkout {
+        kind: String,
+        duration: WorkoutDuration,
+    },
+    /// a propper training session, kept distinct from `DurationWorkout` so
+    /// analytics can tell the two apart without matching on `kind`
+    Training(WorkoutDuration),
+    /// a timed activity over a distance, e.g. running, rowing or biking
+    TimeDistance {
+        kind: String,
+        duration: Option<WorkoutDuration>,
+        distance: Distance,
+    },
+    /// a strength exercise done for a number of sets and reps
+    SetRep { kind: String, sets: u32, reps: u32 },
+}
+
+impl Activity {
+    /// Whether logging this activity alone is enough to keep the sport
+    /// streak alive, a bike ride (or run/row/swim) only counts once it
+    /// reaches `biking_distance`.
+    pub fn counts_for_streak(&self, biking_distance: Distance) -> bool {
+        match self {
+            Activity::DurationWorkout { .. } | Activity::Training(_) | Activity::SetRep { .. } => {
+                true
+            }
+            Activity::TimeDistance { distance, .. } => *distance >= biking_distance,
+            Activity::Weight(_) | Activity::Steps(_) | Activity::CheatDay => false,
+        }
+    }
+}
+
+impl fmt::Display for Activity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Activity::Weight(weight) => write!(f, "weight: {}", weight),
+            Activity::Steps(steps) => write!(f, "{} steps", steps),
+            Activity::CheatDay => write!(f, "cheat day"),
+            Activity::DurationWorkout { kind, duration } => write!(f, "{}: {}", kind, duration),
+            Activity::Training(duration) => write!(f, "training: {}", duration),
+            Activity::TimeDistance {
+                kind,
+                duration,
+                distance,
+            } => match duration {
+                Some(duration) => write!(f, "{}: {} in {}", kind, distance, duration),
+                None => write!(f, "{}: {}", kind, distance),
+            },
+            Activity::SetRep { kind, sets, reps } => write!(f, "{}: {}x{}", kind, sets, reps),
+        }
+    }
+}
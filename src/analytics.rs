@@ -0,0 +1,278 @@
+//! Aggregate analytics over the logged activities.
+//!
+//! `analyze` used to just print the raw per-day table plus the current
+//! streak. This groups the same data into configurable windows (a week,
+//! a month, or all time) so it can answer "am I improving", not just
+//! "what did I do".
+
+use crate::activity::Activity;
+use crate::HealthTrackerError;
+use chrono::{Datelike, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Week,
+    Month,
+    All,
+}
+
+impl FromStr for Period {
+    type Err = HealthTrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "week" => Ok(Self::Week),
+            "month" => Ok(Self::Month),
+            "all" => Ok(Self::All),
+            other => Err(HealthTrackerError::Dummy(format!(
+                "'{}' is not a period, expected \"week\", \"month\" or \"all\"",
+                other
+            ))),
+        }
+    }
+}
+
+/// A window of days, ordered chronologically so buckets print in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Bucket {
+    Week(i32, u32),
+    Month(i32, u32),
+    All,
+}
+
+impl fmt::Display for Bucket {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Bucket::Week(year, week) => write!(f, "{}-W{:02}", year, week),
+            Bucket::Month(year, month) => write!(f, "{}-{:02}", year, month),
+            Bucket::All => write!(f, "all time"),
+        }
+    }
+}
+
+fn bucket_for(date: NaiveDate, period: Period) -> Bucket {
+    match period {
+        Period::Week => {
+            let iso = date.iso_week();
+            Bucket::Week(iso.year(), iso.week())
+        }
+        Period::Month => Bucket::Month(date.year(), date.month()),
+        Period::All => Bucket::All,
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct BucketSummary {
+    pub biking_count: u32,
+    pub biking_total_km: f64,
+    pub workout_days: u32,
+    pub training_days: u32,
+    first_weight_kg: Option<f64>,
+    last_weight_kg: Option<f64>,
+    first_weight_date: Option<NaiveDate>,
+    /// `(days since the bucket's first weight entry, weight in kg)`,
+    /// kept to fit `weight_slope_kg_per_day`.
+    weight_points: Vec<(i64, f64)>,
+}
+
+impl BucketSummary {
+    pub fn biking_average_km(&self) -> f64 {
+        if self.biking_count == 0 {
+            0.0
+        } else {
+            self.biking_total_km / f64::from(self.biking_count)
+        }
+    }
+
+    /// `(first, last)` weight seen in the window, in that chronological
+    /// order, if any weight was logged at all.
+    pub fn weight_trend_kg(&self) -> Option<(f64, f64)> {
+        self.first_weight_kg.zip(self.last_weight_kg)
+    }
+
+    /// A least-squares fit of weight (kg) against day offset, in kg/day.
+    /// `None` if fewer than two weight entries were logged in the window,
+    /// since a slope needs at least two points.
+    pub fn weight_slope_kg_per_day(&self) -> Option<f64> {
+        let n = self.weight_points.len();
+        if n < 2 {
+            return None;
+        }
+        let n = n as f64;
+        let sum_x: f64 = self.weight_points.iter().map(|(x, _)| *x as f64).sum();
+        let sum_y: f64 = self.weight_points.iter().map(|(_, y)| y).sum();
+        let sum_xy: f64 = self
+            .weight_points
+            .iter()
+            .map(|(x, y)| *x as f64 * y)
+            .sum();
+        let sum_xx: f64 = self.weight_points.iter().map(|(x, _)| (*x as f64).powi(2)).sum();
+
+        let denominator = n * sum_xx - sum_x * sum_x;
+        if denominator == 0.0 {
+            return None;
+        }
+        Some((n * sum_xy - sum_x * sum_y) / denominator)
+    }
+}
+
+/// Folds the history into one [`BucketSummary`] per window. Each day's
+/// activities must be visited in date order so "first vs. last weight"
+/// comes out right.
+pub fn summarize(
+    mut days: Vec<(NaiveDate, Vec<&Activity>)>,
+    period: Period,
+) -> BTreeMap<Bucket, BucketSummary> {
+    days.sort_by_key(|(date, _)| *date);
+
+    let mut buckets: BTreeMap<Bucket, BucketSummary> = BTreeMap::new();
+    for (date, activities) in days {
+        let summary = buckets.entry(bucket_for(date, period)).or_default();
+        let mut worked_out = false;
+        let mut trained = false;
+
+        for activity in activities {
+            match activity {
+                Activity::Weight(weight) => {
+                    let weight_kg = weight.kg();
+                    summary.first_weight_kg.get_or_insert(weight_kg);
+                    summary.last_weight_kg = Some(weight_kg);
+                    let anchor = *summary.first_weight_date.get_or_insert(date);
+                    summary
+                        .weight_points
+                        .push(((date - anchor).num_days(), weight_kg));
+                }
+                Activity::TimeDistance { kind, distance, .. } if kind == "biking" => {
+                    summary.biking_count += 1;
+                    summary.biking_total_km += distance.km();
+                }
+                Activity::Training(_) => {
+                    trained = true;
+                }
+                Activity::DurationWorkout { .. } => {
+                    worked_out = true;
+                }
+                Activity::TimeDistance { .. }
+                | Activity::Steps(_)
+                | Activity::CheatDay
+                | Activity::SetRep { .. } => {}
+            }
+        }
+        summary.workout_days += u32::from(worked_out);
+        summary.training_days += u32::from(trained);
+    }
+    buckets
+}
+
+/// The longest run of consecutive sport days across the whole history,
+/// as opposed to `get_sport_streak`, which only looks at the run ending
+/// today.
+pub fn longest_streak(sport_days: &[NaiveDate]) -> u32 {
+    let mut sorted = sport_days.to_vec();
+    sorted.sort();
+    sorted.dedup();
+
+    let mut longest = 0;
+    let mut current = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for date in sorted {
+        current = match previous {
+            Some(prev) if prev.succ() == date => current + 1,
+            _ => 1,
+        };
+        longest = longest.max(current);
+        previous = Some(date);
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::units::{Distance, Weight};
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    #[test]
+    fn longest_streak_finds_the_longest_run_of_consecutive_days() {
+        let days = [
+            date(2024, 1, 1),
+            date(2024, 1, 2),
+            date(2024, 1, 3),
+            date(2024, 1, 5),
+            date(2024, 1, 6),
+        ];
+        assert_eq!(longest_streak(&days), 3);
+    }
+
+    #[test]
+    fn longest_streak_is_zero_for_no_days() {
+        assert_eq!(longest_streak(&[]), 0);
+    }
+
+    #[test]
+    fn bucket_for_groups_by_iso_week_and_by_month() {
+        assert_eq!(
+            bucket_for(date(2024, 2, 19), Period::Week),
+            Bucket::Week(2024, 8)
+        );
+        assert_eq!(
+            bucket_for(date(2024, 2, 19), Period::Month),
+            Bucket::Month(2024, 2)
+        );
+        assert_eq!(bucket_for(date(2024, 2, 19), Period::All), Bucket::All);
+    }
+
+    #[test]
+    fn summarize_sums_biking_distance_and_tracks_weight_trend_within_a_bucket() {
+        let first_weight = Activity::Weight(Weight::from_kg(80.0));
+        let last_weight = Activity::Weight(Weight::from_kg(79.0));
+        let biking = Activity::TimeDistance {
+            kind: "biking".to_string(),
+            duration: None,
+            distance: Distance::from_km(15.0),
+        };
+        let days = vec![
+            (date(2024, 2, 1), vec![&first_weight]),
+            (date(2024, 2, 10), vec![&biking]),
+            (date(2024, 2, 20), vec![&last_weight]),
+        ];
+
+        let buckets = summarize(days, Period::Month);
+        let summary = &buckets[&Bucket::Month(2024, 2)];
+
+        assert_eq!(summary.biking_count, 1);
+        assert!((summary.biking_total_km - 15.0).abs() < f64::EPSILON);
+        assert_eq!(summary.weight_trend_kg(), Some((80.0, 79.0)));
+    }
+
+    #[test]
+    fn weight_slope_fits_a_steady_loss_of_half_a_kilo_a_day() {
+        let day0 = Activity::Weight(Weight::from_kg(80.0));
+        let day2 = Activity::Weight(Weight::from_kg(79.0));
+        let day4 = Activity::Weight(Weight::from_kg(78.0));
+        let days = vec![
+            (date(2024, 2, 1), vec![&day0]),
+            (date(2024, 2, 3), vec![&day2]),
+            (date(2024, 2, 5), vec![&day4]),
+        ];
+
+        let buckets = summarize(days, Period::All);
+        let slope = buckets[&Bucket::All].weight_slope_kg_per_day().unwrap();
+        assert!((slope - -0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn weight_slope_is_none_with_fewer_than_two_points() {
+        let only_weight = Activity::Weight(Weight::from_kg(80.0));
+        let days = vec![(date(2024, 2, 1), vec![&only_weight])];
+
+        let buckets = summarize(days, Period::All);
+        assert_eq!(buckets[&Bucket::All].weight_slope_kg_per_day(), None);
+    }
+}
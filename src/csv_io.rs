@@ -0,0 +1,278 @@
+//! CSV import and export, plus a `--legacy` importer for the older
+//! per-measurement files this tool used before activities were unified
+//! into one store (one file for steps, one for weight, one for biking).
+
+use crate::activity::Activity;
+use crate::units::{Distance, Weight, WorkoutDuration};
+use crate::{HealthTrackerError, History};
+use chrono::NaiveDate;
+use csv::{Reader, Writer};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// One flattened row of the unified CSV schema: the date, the activity's
+/// kind and label, and one column per activity field, left empty where
+/// that kind of activity has no such field.
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvRow {
+    date: NaiveDate,
+    kind: String,
+    label: Option<String>,
+    weight_kg: Option<f64>,
+    steps: Option<u32>,
+    duration_min: Option<u32>,
+    distance_km: Option<f64>,
+    sets: Option<u32>,
+    reps: Option<u32>,
+}
+
+impl CsvRow {
+    fn from_activity(date: NaiveDate, activity: &Activity) -> Self {
+        let mut row = Self {
+            date,
+            kind: String::new(),
+            label: None,
+            weight_kg: None,
+            steps: None,
+            duration_min: None,
+            distance_km: None,
+            sets: None,
+            reps: None,
+        };
+        match activity {
+            Activity::Weight(weight) => {
+                row.kind = "weight".to_string();
+                row.weight_kg = Some(weight.kg());
+            }
+            Activity::Steps(steps) => {
+                row.kind = "steps".to_string();
+                row.steps = Some(*steps);
+            }
+            Activity::CheatDay => {
+                row.kind = "cheatday".to_string();
+            }
+            Activity::DurationWorkout { kind, duration } => {
+                row.kind = "duration_workout".to_string();
+                row.label = Some(kind.clone());
+                row.duration_min = Some(duration.minutes());
+            }
+            Activity::Training(duration) => {
+                row.kind = "training".to_string();
+                row.duration_min = Some(duration.minutes());
+            }
+            Activity::TimeDistance {
+                kind,
+                duration,
+                distance,
+            } => {
+                row.kind = "time_distance".to_string();
+                row.label = Some(kind.clone());
+                row.duration_min = duration.map(WorkoutDuration::minutes);
+                row.distance_km = Some(distance.km());
+            }
+            Activity::SetRep { kind, sets, reps } => {
+                row.kind = "set_rep".to_string();
+                row.label = Some(kind.clone());
+                row.sets = Some(*sets);
+                row.reps = Some(*reps);
+            }
+        }
+        row
+    }
+
+    fn into_activity(self) -> Result<(NaiveDate, Activity), HealthTrackerError> {
+        let activity = match self.kind.as_str() {
+            "weight" => Activity::Weight(Weight::from_kg(field(self.weight_kg, "weight_kg")?)),
+            "steps" => Activity::Steps(field(self.steps, "steps")?),
+            "cheatday" => Activity::CheatDay,
+            "duration_workout" => Activity::DurationWorkout {
+                kind: field(self.label, "label")?,
+                duration: WorkoutDuration::from_minutes(field(self.duration_min, "duration_min")?),
+            },
+            "training" => Activity::Training(WorkoutDuration::from_minutes(field(
+                self.duration_min,
+                "duration_min",
+            )?)),
+            "time_distance" => Activity::TimeDistance {
+                kind: field(self.label, "label")?,
+                duration: self.duration_min.map(WorkoutDuration::from_minutes),
+                distance: Distance::from_km(field(self.distance_km, "distance_km")?),
+            },
+            "set_rep" => Activity::SetRep {
+                kind: field(self.label, "label")?,
+                sets: field(self.sets, "sets")?,
+                reps: field(self.reps, "reps")?,
+            },
+            other => {
+                return Err(HealthTrackerError::Dummy(format!(
+                    "unknown activity kind '{}'",
+                    other
+                )))
+            }
+        };
+        Ok((self.date, activity))
+    }
+}
+
+fn field<T>(value: Option<T>, name: &str) -> Result<T, HealthTrackerError> {
+    value.ok_or_else(|| HealthTrackerError::Dummy(format!("row is missing '{}'", name)))
+}
+
+fn csv_error(error: csv::Error) -> HealthTrackerError {
+    HealthTrackerError::Dummy(error.to_string())
+}
+
+pub(crate) fn export(history: &History, path: &Path) -> Result<(), HealthTrackerError> {
+    let mut writer = Writer::from_path(path).map_err(csv_error)?;
+    let mut rows = history
+        .iter_activities()
+        .map(|(date, activity)| (date, CsvRow::from_activity(date, activity)))
+        .collect::<Vec<_>>();
+    rows.sort_by_key(|(date, _)| *date);
+    for (_, row) in rows {
+        writer.serialize(row).map_err(csv_error)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+pub(crate) fn import(history: &mut History, path: &Path) -> Result<(), HealthTrackerError> {
+    let mut reader = Reader::from_path(path).map_err(csv_error)?;
+    for result in reader.deserialize::<CsvRow>() {
+        let (date, activity) = result.map_err(csv_error)?.into_activity()?;
+        history.insert_on_date(date, activity)?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyStepsRow {
+    date: NaiveDate,
+    steps: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyWeightRow {
+    date: NaiveDate,
+    weight: f64,
+}
+
+#[derive(Debug, Deserialize)]
+struct LegacyBikingRow {
+    date: NaiveDate,
+    distance: f64,
+}
+
+/// Merge the older one-measurement-per-file layout into `history`.
+/// Distances for the same date are summed, weight is last-write-wins
+/// (later rows replace earlier ones for that date), and only the fields
+/// present in the given files are touched.
+pub(crate) fn import_legacy(
+    history: &mut History,
+    steps_file: Option<&Path>,
+    weight_file: Option<&Path>,
+    biking_file: Option<&Path>,
+) -> Result<(), HealthTrackerError> {
+    if let Some(path) = steps_file {
+        let mut reader = Reader::from_path(path).map_err(csv_error)?;
+        for result in reader.deserialize::<LegacyStepsRow>() {
+            let row = result.map_err(csv_error)?;
+            history.replace_on_date(row.date, Activity::Steps(row.steps))?;
+        }
+    }
+
+    if let Some(path) = weight_file {
+        let mut reader = Reader::from_path(path).map_err(csv_error)?;
+        for result in reader.deserialize::<LegacyWeightRow>() {
+            let row = result.map_err(csv_error)?;
+            history.replace_on_date(row.date, Activity::Weight(Weight::from_kg(row.weight)))?;
+        }
+    }
+
+    if let Some(path) = biking_file {
+        let mut reader = Reader::from_path(path).map_err(csv_error)?;
+        let mut totals: HashMap<NaiveDate, f64> = HashMap::new();
+        for result in reader.deserialize::<LegacyBikingRow>() {
+            let row = result.map_err(csv_error)?;
+            *totals.entry(row.date).or_insert(0.0) += row.distance;
+        }
+        for (date, total_km) in totals {
+            history.replace_on_date(
+                date,
+                Activity::TimeDistance {
+                    kind: "biking".to_string(),
+                    duration: None,
+                    distance: Distance::from_km(total_km),
+                },
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Writes `contents` to a uniquely-named file under the system temp
+    /// dir and returns its path; the caller doesn't need to clean up,
+    /// the OS reclaims `std::env::temp_dir()` eventually.
+    fn write_temp_csv(contents: &str) -> std::path::PathBuf {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+        let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("healthtracker-csv-io-test-{}.csv", n));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn activity_round_trips_through_a_csv_row() {
+        let date = NaiveDate::from_ymd(2024, 2, 19);
+        let activity = Activity::TimeDistance {
+            kind: "biking".to_string(),
+            duration: Some(WorkoutDuration::from_minutes(30)),
+            distance: Distance::from_km(12.5),
+        };
+        let row = CsvRow::from_activity(date, &activity);
+        let (round_tripped_date, round_tripped_activity) = row.into_activity().unwrap();
+        assert_eq!(round_tripped_date, date);
+        assert_eq!(round_tripped_activity, activity);
+    }
+
+    #[test]
+    fn legacy_biking_distances_are_summed_per_date() {
+        let path = write_temp_csv("date,distance\n2024-02-19,5.0\n2024-02-19,3.5\n");
+        let mut history = History::default();
+        import_legacy(&mut history, None, None, Some(path.as_path())).unwrap();
+
+        let activities = history.iter_activities().collect::<Vec<_>>();
+        assert_eq!(activities.len(), 1);
+        let (date, activity) = activities[0];
+        assert_eq!(date, NaiveDate::from_ymd(2024, 2, 19));
+        match activity {
+            Activity::TimeDistance { distance, .. } => {
+                assert!((distance.km() - 8.5).abs() < f64::EPSILON)
+            }
+            other => panic!("expected a TimeDistance activity, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn legacy_weight_on_the_same_date_is_last_write_wins() {
+        let path = write_temp_csv("date,weight\n2024-02-19,80.0\n2024-02-19,79.5\n");
+        let mut history = History::default();
+        import_legacy(&mut history, None, Some(path.as_path()), None).unwrap();
+
+        let activities = history.iter_activities().collect::<Vec<_>>();
+        assert_eq!(activities.len(), 1);
+        match activities[0].1 {
+            Activity::Weight(weight) => assert!((weight.kg() - 79.5).abs() < f64::EPSILON),
+            other => panic!("expected a Weight activity, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! A timezone-aware timestamp.
+
+use crate::HealthTrackerError;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Two `DateTimeTz` are equal, ordered and hashed purely by the instant
+/// they represent — `zone` only affects how that instant is displayed
+/// and which calendar day `date_in_zone` reports, so it's deliberately
+/// excluded here to keep `Eq`/`Ord`/`Hash` consistent with each other.
+#[derive(Debug, Clone, Copy)]
+pub struct DateTimeTz {
+    instant: DateTime<Utc>,
+    zone: Tz,
+}
+
+impl PartialEq for DateTimeTz {
+    fn eq(&self, other: &Self) -> bool {
+        self.instant == other.instant
+    }
+}
+
+impl Eq for DateTimeTz {}
+
+impl Hash for DateTimeTz {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.instant.hash(state);
+    }
+}
+
+impl PartialOrd for DateTimeTz {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DateTimeTz {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.instant.cmp(&other.instant)
+    }
+}
+
+impl DateTimeTz {
+    pub fn new(instant: DateTime<Utc>, zone: Tz) -> Self {
+        Self { instant, zone }
+    }
+
+    /// The current instant, tagged with the system's local timezone.
+    pub fn now_local() -> Result<Self, HealthTrackerError> {
+        let zone = local_timezone()?;
+        Ok(Self::new(Utc::now(), zone))
+    }
+
+    /// Midnight, local time, on `date` — used by the `--date` short form
+    /// that only gives a day, not a time.
+    pub fn midnight_local(date: NaiveDate) -> Result<Self, HealthTrackerError> {
+        let zone = local_timezone()?;
+        let local_midnight = date.and_time(NaiveTime::from_hms(0, 0, 0));
+        let instant = zone
+            .from_local_datetime(&local_midnight)
+            .single()
+            .ok_or_else(|| {
+                HealthTrackerError::Timezone(format!(
+                    "midnight on {} is ambiguous or doesn't exist in {}",
+                    date, zone
+                ))
+            })?
+            .with_timezone(&Utc);
+        Ok(Self::new(instant, zone))
+    }
+
+    /// The calendar date this timestamp falls on, in its own timezone —
+    /// this is what `History` groups records by.
+    pub fn date_in_zone(&self) -> NaiveDate {
+        self.instant.with_timezone(&self.zone).date_naive()
+    }
+}
+
+fn local_timezone() -> Result<Tz, HealthTrackerError> {
+    let name = iana_time_zone::get_timezone()
+        .map_err(|e| HealthTrackerError::Timezone(e.to_string()))?;
+    name.parse::<Tz>()
+        .map_err(|e| HealthTrackerError::Timezone(e.to_string()))
+}
+
+impl fmt::Display for DateTimeTz {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.instant.to_rfc3339(), self.zone.name())
+    }
+}
+
+// Stored as `"<RFC3339> <Timezone Name>"`, e.g.
+// `2024-02-19T14:24:52+00:00 America/New_York`.
+impl Serialize for DateTimeTz {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for DateTimeTz {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(DateTimeTzVisitor)
+    }
+}
+
+struct DateTimeTzVisitor;
+
+impl<'de> Visitor<'de> for DateTimeTzVisitor {
+    type Value = DateTimeTz;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(r#"a string like "2024-02-19T14:24:52Z America/New_York""#)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let (rfc3339, zone_name) = v
+            .rsplit_once(' ')
+            .ok_or_else(|| de::Error::custom(format!("'{}' has no timezone name", v)))?;
+        let instant = DateTime::parse_from_rfc3339(rfc3339)
+            .map_err(de::Error::custom)?
+            .with_timezone(&Utc);
+        let zone = zone_name.parse::<Tz>().map_err(de::Error::custom)?;
+        Ok(DateTimeTz::new(instant, zone))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serde_round_trips_through_ron() {
+        let original = DateTimeTz::new(
+            Utc.ymd(2024, 2, 19).and_hms(14, 24, 52),
+            "America/New_York".parse().unwrap(),
+        );
+        let serialized = ron::ser::to_string(&original).unwrap();
+        let deserialized: DateTimeTz = ron::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, original);
+    }
+
+    #[test]
+    fn equality_and_ordering_ignore_the_display_zone() {
+        let instant = Utc.ymd(2024, 2, 19).and_hms(14, 24, 52);
+        let in_new_york = DateTimeTz::new(instant, "America/New_York".parse().unwrap());
+        let in_utc = DateTimeTz::new(instant, "UTC".parse().unwrap());
+
+        assert_eq!(in_new_york, in_utc);
+        assert_eq!(in_new_york.cmp(&in_utc), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn date_in_zone_uses_the_stored_timezone() {
+        // 1am UTC is still the previous evening in New York.
+        let timestamp = DateTimeTz::new(
+            Utc.ymd(2024, 2, 19).and_hms(1, 0, 0),
+            "America/New_York".parse().unwrap(),
+        );
+        assert_eq!(timestamp.date_in_zone(), NaiveDate::from_ymd(2024, 2, 18));
+    }
+}
@@ -1,17 +1,34 @@
 use chrono::prelude::*;
 use prettytable::{cell, format, row, Table};
-use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
-use std::fs::{read_to_string, File};
+use std::fs::{read_to_string, OpenOptions};
 use std::io::prelude::*;
 use std::io::BufWriter;
 
+pub mod activity;
+pub mod analytics;
+pub mod csv_io;
+pub mod datetime;
+pub mod record;
+pub mod units;
+
+use std::str::FromStr;
+
+use activity::Activity;
+use datetime::DateTimeTz;
+use record::{Record, RecordBody, RecordId};
+
 pub const DATE_FORMAT: &str = "%Y-%m-%d";
 const DATA_FILE_NAME: &str = "data.ron";
-const BIKING_DISTANCE: f32 = 10.0;
-const CHECK: &str = "✔";
-const FAIL: &str = "✘";
+
+/// A bike ride (or run, row or swim) counts towards the sport streak if
+/// that single activity covers at least this distance.
+/// Not a `const` because `Distance::from_km` isn't a `const fn` (it goes
+/// through `dimensioned`'s operator overloads).
+fn biking_distance_threshold() -> units::Distance {
+    units::Distance::from_km(10.0)
+}
 
 #[derive(Debug)]
 pub enum HealthTrackerError {
@@ -20,6 +37,7 @@ pub enum HealthTrackerError {
     XDGBaseDirectories(String),
     IOError(String),
     Ron(String),
+    Timezone(String),
 }
 
 impl std::error::Error for HealthTrackerError {}
@@ -32,6 +50,7 @@ impl fmt::Display for HealthTrackerError {
             Self::XDGBaseDirectories(e) => write!(f, "XDG BaseDirectories Error: {}", e),
             Self::IOError(e) => write!(f, "IO Error: {}", e),
             Self::Ron(e) => write!(f, "RON Error: {}", e),
+            Self::Timezone(e) => write!(f, "Timezone Error: {}", e),
         }
     }
 }
@@ -60,116 +79,172 @@ impl From<ron::Error> for HealthTrackerError {
     }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct Day {
-    /// weight of the day
-    weight: Option<f32>,
-    /// did I do my 7 minute workout?
-    workout: bool,
-    /// did I do a propper training session?
-    training: bool,
-    /// how much did I bike on that day?
-    biking: Option<f32>,
-    /// is this a cheatday?
-    #[serde(default)]
-    cheatday: bool,
-}
-
-impl Day {
-    fn new(
-        weight: Option<f32>,
-        workout: bool,
-        training: bool,
-        biking: Option<f32>,
-        cheatday: bool,
-    ) -> Self {
-        Self {
-            weight,
-            workout,
-            training,
-            biking,
-            cheatday,
-        }
-    }
-}
-
-#[derive(Debug, Deserialize, Serialize)]
+/// The in-memory, folded view of the append-only record log: the latest
+/// surviving activity for every `RecordId` that hasn't been tombstoned.
+/// `save` only ever appends `pending` to the data file — it never
+/// rewrites history that's already on disk.
+#[derive(Debug, Default)]
 struct History {
-    map: HashMap<NaiveDate, Day>,
+    entries: HashMap<RecordId, (DateTimeTz, Activity)>,
+    pending: Vec<Record>,
 }
 
 impl History {
     fn load() -> Result<Self, HealthTrackerError> {
         let xdg_basedir = xdg::BaseDirectories::with_prefix(clap::crate_name!())?;
-        let history = match xdg_basedir.find_data_file(DATA_FILE_NAME) {
-            Some(p) => ron::from_str::<History>(&read_to_string(p)?)?,
-            None => History {
-                map: HashMap::new(),
-            },
-        };
-        Ok(history)
+        let mut records: Vec<Record> = Vec::new();
+        if let Some(path) = xdg_basedir.find_data_file(DATA_FILE_NAME) {
+            for line in read_to_string(path)?.lines() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                records.push(ron::from_str(line)?);
+            }
+        }
+        Ok(Self::from_records(records))
+    }
+
+    /// Folds an append-only log into the latest surviving activity per
+    /// id: a later `Entry` for the same id overwrites an earlier one
+    /// (an edit), and a `Tombstone` removes it (a delete). `records`
+    /// must be in the order they were logged.
+    fn from_records(records: Vec<Record>) -> Self {
+        let mut entries = HashMap::new();
+        for record in records {
+            match record.body {
+                RecordBody::Entry(activity) => {
+                    entries.insert(record.id, (record.timestamp, activity));
+                }
+                RecordBody::Tombstone => {
+                    entries.remove(&record.id);
+                }
+            }
+        }
+        Self {
+            entries,
+            pending: Vec::new(),
+        }
     }
 }
 
 impl History {
-    fn log_weight(&mut self, date: NaiveDate, weight: f32) {
-        let day = if let Some(day) = self.map.get(&date) {
-            Day::new(
-                Some(weight),
-                day.workout,
-                day.training,
-                day.biking,
-                day.cheatday,
-            )
-        } else {
-            Day::new(Some(weight), false, false, None, false)
-        };
-        self.map.insert(date, day);
+    /// Logs a new activity, returning the `RecordId` it was assigned so
+    /// it can later be passed to `edit_entry` or `delete_entry`.
+    fn push_entry(&mut self, timestamp: DateTimeTz, activity: Activity) -> RecordId {
+        let id = RecordId::new();
+        self.entries.insert(id, (timestamp, activity.clone()));
+        self.pending.push(Record {
+            id,
+            timestamp,
+            body: RecordBody::Entry(activity),
+        });
+        id
+    }
+
+    fn edit_entry(
+        &mut self,
+        id: RecordId,
+        timestamp: DateTimeTz,
+        activity: Activity,
+    ) -> Result<(), HealthTrackerError> {
+        if !self.entries.contains_key(&id) {
+            return Err(HealthTrackerError::Dummy(format!(
+                "no record with id {}",
+                id
+            )));
+        }
+        self.entries.insert(id, (timestamp, activity.clone()));
+        self.pending.push(Record {
+            id,
+            timestamp,
+            body: RecordBody::Entry(activity),
+        });
+        Ok(())
     }
 
-    fn log_sport(
+    fn delete_entry(&mut self, id: RecordId) -> Result<(), HealthTrackerError> {
+        if self.entries.remove(&id).is_none() {
+            return Err(HealthTrackerError::Dummy(format!(
+                "no record with id {}",
+                id
+            )));
+        }
+        self.pending.push(Record {
+            id,
+            timestamp: DateTimeTz::now_local()?,
+            body: RecordBody::Tombstone,
+        });
+        Ok(())
+    }
+
+    /// Appends `activity` to `date` at local midnight, used by CSV
+    /// import where the source rows only carry a date.
+    pub(crate) fn insert_on_date(
         &mut self,
         date: NaiveDate,
-        workout: bool,
-        training: bool,
-        biking: Option<f32>,
-        cheatday: bool,
-    ) {
-        let day = if let Some(day) = self.map.get(&date) {
-            Day::new(
-                day.weight,
-                day.workout || workout,
-                day.training || training,
-                match biking {
-                    Some(d) => Some(d),
-                    None => day.biking,
-                },
-                day.cheatday || cheatday,
-            )
-        } else {
-            Day::new(None, workout, training, biking, cheatday)
+        activity: Activity,
+    ) -> Result<RecordId, HealthTrackerError> {
+        let timestamp = DateTimeTz::midnight_local(date)?;
+        Ok(self.push_entry(timestamp, activity))
+    }
+
+    /// Like `insert_on_date`, but first removes any existing entry of
+    /// the same activity kind on that date — used for fields the legacy
+    /// importer merges last-write-wins, such as weight.
+    pub(crate) fn replace_on_date(
+        &mut self,
+        date: NaiveDate,
+        activity: Activity,
+    ) -> Result<(), HealthTrackerError> {
+        let same_kind = |existing: &Activity| match (existing, &activity) {
+            (Activity::TimeDistance { kind: a, .. }, Activity::TimeDistance { kind: b, .. })
+            | (Activity::DurationWorkout { kind: a, .. }, Activity::DurationWorkout { kind: b, .. })
+            | (Activity::SetRep { kind: a, .. }, Activity::SetRep { kind: b, .. }) => a == b,
+            _ => std::mem::discriminant(existing) == std::mem::discriminant(&activity),
         };
-        self.map.insert(date, day);
+        let stale_ids = self
+            .entries
+            .iter()
+            .filter(|(_, (timestamp, existing))| {
+                timestamp.date_in_zone() == date && same_kind(existing)
+            })
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        for id in stale_ids {
+            self.delete_entry(id)?;
+        }
+        self.insert_on_date(date, activity)?;
+        Ok(())
+    }
+
+    pub(crate) fn iter_activities(&self) -> impl Iterator<Item = (NaiveDate, &Activity)> {
+        self.entries
+            .values()
+            .map(|(timestamp, activity)| (timestamp.date_in_zone(), activity))
     }
 }
 
 impl History {
     fn save(&self) -> Result<(), HealthTrackerError> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
         let xdg_basedir = xdg::BaseDirectories::with_prefix(clap::crate_name!())?;
         let path = xdg_basedir.place_data_file(DATA_FILE_NAME)?;
-        let file = File::create(&path)?;
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
         let mut writer = BufWriter::new(&file);
-        write!(&mut writer, "{}", ron::ser::to_string(&self)?)?;
+        for record in &self.pending {
+            writeln!(&mut writer, "{}", ron::ser::to_string(record)?)?;
+        }
         Ok(())
     }
 
     fn get_sport_streak(&self, date: NaiveDate) -> u32 {
-        let day = match self.map.get(&date) {
-            Some(d) => d,
-            None => return 0,
-        };
-
-        if day.workout || day.training || day.biking.unwrap_or(0.0) >= BIKING_DISTANCE {
+        let did_sport = self.entries.values().any(|(timestamp, activity)| {
+            timestamp.date_in_zone() == date
+                && activity.counts_for_streak(biking_distance_threshold())
+        });
+        if did_sport {
             1 + self.get_sport_streak(date.pred())
         } else {
             0
@@ -179,88 +254,233 @@ impl History {
     fn get_days_table(&self) -> Table {
         let mut table = Table::new();
         table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
-        table.set_titles(row![
-            "date",
-            "weight [kg]",
-            "workout",
-            "training",
-            "biking [km]",
-            "cheat day"
-        ]);
+        table.set_titles(row!["id", "date", "activity"]);
 
-        let mut history_vec = self.map.iter().collect::<Vec<_>>();
-        history_vec.sort_by(|a, b| a.0.cmp(b.0));
-        for (date, day) in history_vec.iter() {
-            let weight = match day.weight {
-                Some(w) => w.to_string(),
-                None => "".to_string(),
-            };
-            let biking = match day.biking {
-                Some(b) => b.to_string(),
-                None => "".to_string(),
-            };
-            table.add_row(row![
-                date,
-                weight,
-                get_mark(day.workout),
-                get_mark(day.training),
-                biking,
-                get_mark(day.cheatday),
-            ]);
+        let mut rows = self.entries.iter().collect::<Vec<_>>();
+        rows.sort_by_key(|(_, (timestamp, _))| *timestamp);
+        for (id, (timestamp, activity)) in rows {
+            table.add_row(row![id, timestamp.date_in_zone(), activity]);
         }
 
         table
     }
-}
 
-fn get_mark(input: bool) -> String {
-    if input {
-        CHECK.to_string()
-    } else {
-        FAIL.to_string()
+    /// Every logged day, each with the activities recorded on it.
+    fn days(&self) -> Vec<(NaiveDate, Vec<&Activity>)> {
+        let mut days: HashMap<NaiveDate, Vec<&Activity>> = HashMap::new();
+        for (timestamp, activity) in self.entries.values() {
+            days.entry(timestamp.date_in_zone()).or_default().push(activity);
+        }
+        days.into_iter().collect()
+    }
+
+    /// Every day on which at least one activity counted towards the
+    /// sport streak, used to find the longest historical streak.
+    fn sport_days(&self) -> Vec<NaiveDate> {
+        self.days()
+            .into_iter()
+            .filter(|(_, activities)| {
+                activities
+                    .iter()
+                    .any(|activity| activity.counts_for_streak(biking_distance_threshold()))
+            })
+            .map(|(date, _)| date)
+            .collect()
     }
 }
 
-fn get_date(date_str: Option<String>) -> Result<NaiveDate, HealthTrackerError> {
+fn get_datetime(date_str: Option<String>) -> Result<DateTimeTz, HealthTrackerError> {
     match date_str {
-        Some(s) => Ok(NaiveDate::parse_from_str(&s, DATE_FORMAT)?),
-        None => Ok(Local::today().naive_local()),
+        Some(s) => DateTimeTz::midnight_local(NaiveDate::parse_from_str(&s, DATE_FORMAT)?),
+        None => DateTimeTz::now_local(),
     }
 }
 
-pub fn log_weight(weight: f32, date_str: Option<String>) -> Result<(), HealthTrackerError> {
+pub fn log_activity(
+    activity: Activity,
+    date_str: Option<String>,
+) -> Result<RecordId, HealthTrackerError> {
     let mut history = History::load()?;
-    let date = get_date(date_str)?;
-    history.log_weight(date, weight);
+    let timestamp = get_datetime(date_str)?;
+    let id = history.push_entry(timestamp, activity);
     history.save()?;
 
-    Ok(())
+    Ok(id)
 }
 
-pub fn log_sport(
-    workout: bool,
-    training: bool,
-    biking: Option<f32>,
-    cheatday: bool,
+pub fn edit_activity(
+    id: RecordId,
+    activity: Activity,
     date_str: Option<String>,
 ) -> Result<(), HealthTrackerError> {
     let mut history = History::load()?;
-    let date = get_date(date_str)?;
-    history.log_sport(date, workout, training, biking, cheatday);
-    history.save()?;
+    let timestamp = match date_str {
+        Some(s) => get_datetime(Some(s))?,
+        None => history
+            .entries
+            .get(&id)
+            .map(|(timestamp, _)| *timestamp)
+            .ok_or_else(|| HealthTrackerError::Dummy(format!("no record with id {}", id)))?,
+    };
+    history.edit_entry(id, timestamp, activity)?;
+    history.save()
+}
 
-    Ok(())
+pub fn delete_activity(id: RecordId) -> Result<(), HealthTrackerError> {
+    let mut history = History::load()?;
+    history.delete_entry(id)?;
+    history.save()
+}
+
+pub fn export_csv(path: &std::path::Path) -> Result<(), HealthTrackerError> {
+    let history = History::load()?;
+    csv_io::export(&history, path)
+}
+
+pub fn import_csv(path: &std::path::Path) -> Result<(), HealthTrackerError> {
+    let mut history = History::load()?;
+    csv_io::import(&mut history, path)?;
+    history.save()
+}
+
+pub fn import_csv_legacy(
+    steps_file: Option<&std::path::Path>,
+    weight_file: Option<&std::path::Path>,
+    biking_file: Option<&std::path::Path>,
+) -> Result<(), HealthTrackerError> {
+    let mut history = History::load()?;
+    csv_io::import_legacy(&mut history, steps_file, weight_file, biking_file)?;
+    history.save()
 }
 
-pub fn analyze() -> Result<(), HealthTrackerError> {
+pub fn analyze(period_str: Option<String>) -> Result<(), HealthTrackerError> {
     let history = History::load()?;
 
     let table = history.get_days_table();
     table.printstd();
 
-    let sport_streak = history.get_sport_streak(get_date(None)?);
-
+    let sport_streak = history.get_sport_streak(get_datetime(None)?.date_in_zone());
     println!("Current sport streak: {}", sport_streak);
 
+    let longest_streak = analytics::longest_streak(&history.sport_days());
+    println!("Longest sport streak: {}", longest_streak);
+
+    let period = match period_str {
+        Some(s) => analytics::Period::from_str(&s)?,
+        None => analytics::Period::All,
+    };
+
+    for (bucket, summary) in analytics::summarize(history.days(), period) {
+        let mut table = Table::new();
+        table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+        table.set_titles(row![bucket.to_string(), ""]);
+        table.add_row(row![
+            "biking total",
+            format!("{:.1} km", summary.biking_total_km)
+        ]);
+        table.add_row(row![
+            "biking average",
+            format!("{:.1} km", summary.biking_average_km())
+        ]);
+        table.add_row(row!["workout days", summary.workout_days]);
+        table.add_row(row!["training days", summary.training_days]);
+        if let Some((first, last)) = summary.weight_trend_kg() {
+            table.add_row(row![
+                "weight trend",
+                format!("{:.1} kg -> {:.1} kg", first, last)
+            ]);
+        }
+        if let Some(slope) = summary.weight_slope_kg_per_day() {
+            table.add_row(row!["weight slope", format!("{:+.2} kg/day", slope)]);
+        }
+        table.printstd();
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use units::Weight;
+
+    fn timestamp(hour: u32) -> DateTimeTz {
+        DateTimeTz::new(
+            Utc.ymd(2024, 2, 19).and_hms(hour, 0, 0),
+            "UTC".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn edit_entry_replaces_the_activity_for_that_id() {
+        let mut history = History::default();
+        let id = history.push_entry(timestamp(8), Activity::Weight(Weight::from_kg(80.0)));
+
+        history
+            .edit_entry(id, timestamp(9), Activity::Weight(Weight::from_kg(81.0)))
+            .unwrap();
+
+        assert_eq!(history.entries.len(), 1);
+        let (ts, activity) = &history.entries[&id];
+        assert_eq!(*ts, timestamp(9));
+        assert_eq!(*activity, Activity::Weight(Weight::from_kg(81.0)));
+    }
+
+    #[test]
+    fn edit_entry_fails_for_an_unknown_id() {
+        let mut history = History::default();
+        let result = history.edit_entry(RecordId::new(), timestamp(8), Activity::Steps(1000));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn delete_then_reload_omits_the_record() {
+        let mut history = History::default();
+        let id = history.push_entry(timestamp(8), Activity::Steps(1000));
+        history.delete_entry(id).unwrap();
+
+        // Replaying the same log the next `load` would read should fold
+        // away the entry entirely, not just mark it deleted.
+        let reloaded = History::from_records(history.pending.clone());
+        assert!(reloaded.entries.is_empty());
+    }
+
+    #[test]
+    fn reloading_keeps_only_the_last_write_for_an_id() {
+        let id = RecordId::new();
+        let records = vec![
+            Record {
+                id,
+                timestamp: timestamp(8),
+                body: RecordBody::Entry(Activity::Weight(Weight::from_kg(80.0))),
+            },
+            Record {
+                id,
+                timestamp: timestamp(9),
+                body: RecordBody::Entry(Activity::Weight(Weight::from_kg(79.0))),
+            },
+        ];
+
+        let history = History::from_records(records);
+
+        assert_eq!(history.entries.len(), 1);
+        let (_, activity) = &history.entries[&id];
+        assert_eq!(*activity, Activity::Weight(Weight::from_kg(79.0)));
+    }
+
+    #[test]
+    fn replace_on_date_drops_same_kind_entries_before_inserting() {
+        let mut history = History::default();
+        history
+            .insert_on_date(NaiveDate::from_ymd(2024, 2, 19), Activity::Steps(1000))
+            .unwrap();
+
+        history
+            .replace_on_date(NaiveDate::from_ymd(2024, 2, 19), Activity::Steps(2000))
+            .unwrap();
+
+        let activities = history.iter_activities().collect::<Vec<_>>();
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].1, &Activity::Steps(2000));
+    }
+}
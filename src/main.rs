@@ -1,5 +1,43 @@
 use chrono::prelude::*;
 use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg, SubCommand};
+use healthtracker::activity::Activity;
+use healthtracker::record::RecordId;
+use healthtracker::units::{Distance, WorkoutDuration};
+use std::str::FromStr;
+
+/// `running`, `rowing` and `swimming` are all a timed activity over a
+/// distance, differing only in their `kind` tag, so they share one
+/// subcommand definition and one handler.
+const TIME_DISTANCE_ACTIVITIES: &[&str] = &["running", "rowing", "swimming"];
+
+/// Logs `activity` as a new record, unless `matches` carries `--id`, in
+/// which case it edits that existing record instead. Either way the
+/// affected record id is printed so it can be passed to `--id` or
+/// `delete` later.
+///
+/// `--date` has a default value, so `occurrences_of` (not `value_of`) is
+/// what tells apart "the user typed `--date`" from "clap filled in
+/// today's date" — an edit with no explicit `--date` must keep the
+/// record's existing timestamp instead of being defaulted to today.
+fn log_or_edit(activity: Activity, matches: &clap::ArgMatches) {
+    let date_str = matches.value_of("date").map(str::to_string);
+    let explicit_date_str = if matches.occurrences_of("date") > 0 {
+        date_str
+    } else {
+        None
+    };
+    match matches.value_of("id") {
+        Some(id) => {
+            let id = RecordId::from_str(id).unwrap();
+            healthtracker::edit_activity(id, activity, explicit_date_str).unwrap();
+            println!("Updated record {}", id);
+        }
+        None => {
+            let id = healthtracker::log_activity(activity, date_str).unwrap();
+            println!("Logged as {}", id);
+        }
+    }
+}
 
 fn main() {
     fn datetime_validator(s: String) -> Result<(), String> {
@@ -12,7 +50,38 @@ fn main() {
         }
     }
 
-    let date_help_str = format!("Date formatted as \"{}\"", healthtracker::DATE_FORMAT);
+    fn weight_validator(s: String) -> Result<(), String> {
+        healthtracker::units::Weight::parse(&s)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn distance_validator(s: String) -> Result<(), String> {
+        Distance::parse(&s).map(|_| ()).map_err(|e| e.to_string())
+    }
+
+    fn duration_validator(s: String) -> Result<(), String> {
+        WorkoutDuration::parse(&s)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    fn sets_reps_validator(s: String) -> Result<(), String> {
+        s.parse::<u32>()
+            .map(|_| ())
+            .map_err(|_| "Must be a whole number".to_string())
+    }
+
+    fn id_validator(s: String) -> Result<(), String> {
+        RecordId::from_str(&s)
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    let date_help_str = format!(
+        "Date formatted as \"{}\". Defaults to today when logging, or to the record's existing date when editing",
+        healthtracker::DATE_FORMAT
+    );
     let current_datetime = Local::today()
         .format(&healthtracker::DATE_FORMAT)
         .to_string();
@@ -25,7 +94,13 @@ fn main() {
         .validator(datetime_validator)
         .help(&date_help_str);
 
-    let matches = App::new(crate_name!())
+    let id_argument = Arg::with_name("id")
+        .long("id")
+        .value_name("ID")
+        .validator(id_validator)
+        .help("Edit the existing record with this id instead of logging a new one");
+
+    let mut app = App::new(crate_name!())
         .version(crate_version!())
         .author(crate_authors!())
         .about(crate_description!())
@@ -35,11 +110,13 @@ fn main() {
                 .version(crate_version!())
                 .about("Track an analyze someones body weight")
                 .arg(&date_argument)
+                .arg(&id_argument)
                 .arg(
                     Arg::with_name("weight")
                         .value_name("WEIGHT")
                         .required(true)
-                        .help("The weight to be entered"),
+                        .validator(weight_validator)
+                        .help("The weight to be entered, e.g. \"75kg\" or \"180lb\""),
                 ),
         )
         .subcommand(
@@ -47,14 +124,23 @@ fn main() {
                 .author(crate_authors!())
                 .version(crate_version!())
                 .about("Track a 7 minute workout for a given day.")
-                .arg(&date_argument),
+                .arg(&date_argument)
+                .arg(&id_argument),
         )
         .subcommand(
             SubCommand::with_name("training")
                 .author(crate_authors!())
                 .version(crate_version!())
                 .about("Track a propper training session for a given day.")
-                .arg(&date_argument),
+                .arg(&date_argument)
+                .arg(&id_argument)
+                .arg(
+                    Arg::with_name("duration")
+                        .value_name("DURATION")
+                        .required(true)
+                        .validator(duration_validator)
+                        .help("How long the session took, e.g. \"45min\""),
+                ),
         )
         .subcommand(
             SubCommand::with_name("biking")
@@ -62,11 +148,13 @@ fn main() {
                 .version(crate_version!())
                 .about("Track a biking distance for a given day")
                 .arg(&date_argument)
+                .arg(&id_argument)
                 .arg(
                     Arg::with_name("distance")
                         .value_name("DISTANCE")
                         .required(true)
-                        .help("The driven distance"),
+                        .validator(distance_validator)
+                        .help("The driven distance, e.g. \"10km\" or \"6mi\""),
                 ),
         )
         .subcommand(
@@ -74,75 +162,241 @@ fn main() {
                 .author(crate_authors!())
                 .version(crate_version!())
                 .about("Define a day as cheat day.")
-                .arg(&date_argument),
+                .arg(&date_argument)
+                .arg(&id_argument),
+        )
+        .subcommand(
+            SubCommand::with_name("strength")
+                .author(crate_authors!())
+                .version(crate_version!())
+                .about("Track a strength exercise done for sets and reps")
+                .arg(&date_argument)
+                .arg(&id_argument)
+                .arg(
+                    Arg::with_name("exercise")
+                        .value_name("EXERCISE")
+                        .required(true)
+                        .help("The exercise performed, e.g. \"squats\""),
+                )
+                .arg(
+                    Arg::with_name("sets")
+                        .value_name("SETS")
+                        .required(true)
+                        .validator(sets_reps_validator)
+                        .help("The number of sets done"),
+                )
+                .arg(
+                    Arg::with_name("reps")
+                        .value_name("REPS")
+                        .required(true)
+                        .validator(sets_reps_validator)
+                        .help("The number of reps done per set"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("delete")
+                .author(crate_authors!())
+                .version(crate_version!())
+                .about("Delete a record by id")
+                .arg(
+                    Arg::with_name("id")
+                        .value_name("ID")
+                        .required(true)
+                        .validator(id_validator)
+                        .help("The id of the record to delete, as shown by `analyze`"),
+                ),
         )
         .subcommand(
             SubCommand::with_name("analyze")
                 .author(crate_authors!())
                 .version(crate_version!())
-                .about("Analyze all tracked data"),
+                .about("Analyze all tracked data")
+                .arg(
+                    Arg::with_name("period")
+                        .long("period")
+                        .value_name("PERIOD")
+                        .possible_values(&["week", "month", "all"])
+                        .default_value("all")
+                        .help("The window to aggregate the summary over"),
+                ),
         )
-        .get_matches();
+        .subcommand(
+            SubCommand::with_name("export")
+                .author(crate_authors!())
+                .version(crate_version!())
+                .about("Export the tracked history to a CSV file")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("FILE")
+                        .required(true)
+                        .help("The CSV file to write"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .author(crate_authors!())
+                .version(crate_version!())
+                .about("Import history from a CSV file")
+                .arg(
+                    Arg::with_name("file")
+                        .value_name("FILE")
+                        .required_unless("legacy")
+                        .help("The CSV file to read, in the format written by `export`"),
+                )
+                .arg(
+                    Arg::with_name("legacy")
+                        .long("legacy")
+                        .help("Read the older one-measurement-per-file layout instead"),
+                )
+                .arg(
+                    Arg::with_name("steps-file")
+                        .long("steps-file")
+                        .value_name("FILE")
+                        .requires("legacy")
+                        .help("Legacy steps file with \"date,steps\" rows"),
+                )
+                .arg(
+                    Arg::with_name("weight-file")
+                        .long("weight-file")
+                        .value_name("FILE")
+                        .requires("legacy")
+                        .help("Legacy weight file with \"date,weight\" rows"),
+                )
+                .arg(
+                    Arg::with_name("biking-file")
+                        .long("biking-file")
+                        .value_name("FILE")
+                        .requires("legacy")
+                        .help("Legacy biking log with \"date,distance\" rows"),
+                ),
+        );
+
+    let time_distance_abouts = TIME_DISTANCE_ACTIVITIES
+        .iter()
+        .map(|kind| format!("Track a {} session for a given day", kind))
+        .collect::<Vec<_>>();
+
+    for (kind, about) in TIME_DISTANCE_ACTIVITIES.iter().zip(&time_distance_abouts) {
+        app = app.subcommand(
+            SubCommand::with_name(kind)
+                .author(crate_authors!())
+                .version(crate_version!())
+                .about(about.as_str())
+                .arg(&date_argument)
+                .arg(&id_argument)
+                .arg(
+                    Arg::with_name("distance")
+                        .value_name("DISTANCE")
+                        .required(true)
+                        .validator(distance_validator)
+                        .help("The distance covered, e.g. \"10km\" or \"6mi\""),
+                )
+                .arg(
+                    Arg::with_name("duration")
+                        .value_name("DURATION")
+                        .required(false)
+                        .validator(duration_validator)
+                        .help("How long it took, e.g. \"30min\""),
+                ),
+        );
+    }
+
+    let matches = app.get_matches();
 
     if let Some(matches) = matches.subcommand_matches("weight") {
-        healthtracker::log_weight(
-            matches.value_of("weight").unwrap().parse::<f32>().unwrap(),
-            Some(matches.value_of("date").unwrap().to_string()),
-        )
-        .unwrap();
+        log_or_edit(
+            Activity::Weight(
+                healthtracker::units::Weight::parse(matches.value_of("weight").unwrap()).unwrap(),
+            ),
+            matches,
+        );
     }
 
     if let Some(matches) = matches.subcommand_matches("workout") {
-        healthtracker::log_sport(
-            true,
-            false,
-            None,
-            false,
-            Some(matches.value_of("date").unwrap().to_string()),
-        )
-        .unwrap();
+        log_or_edit(
+            Activity::DurationWorkout {
+                kind: "7 minute workout".to_string(),
+                duration: WorkoutDuration::from_minutes(7),
+            },
+            matches,
+        );
     }
 
     if let Some(matches) = matches.subcommand_matches("training") {
-        healthtracker::log_sport(
-            false,
-            true,
-            None,
-            false,
-            Some(matches.value_of("date").unwrap().to_string()),
-        )
-        .unwrap();
+        log_or_edit(
+            Activity::Training(
+                WorkoutDuration::parse(matches.value_of("duration").unwrap()).unwrap(),
+            ),
+            matches,
+        );
     }
 
     if let Some(matches) = matches.subcommand_matches("biking") {
-        healthtracker::log_sport(
-            false,
-            false,
-            Some(
-                matches
-                    .value_of("distance")
-                    .unwrap()
-                    .parse::<f32>()
-                    .unwrap(),
-            ),
-            false,
-            Some(matches.value_of("date").unwrap().to_string()),
-        )
-        .unwrap();
+        log_or_edit(
+            Activity::TimeDistance {
+                kind: "biking".to_string(),
+                duration: None,
+                distance: Distance::parse(matches.value_of("distance").unwrap()).unwrap(),
+            },
+            matches,
+        );
     }
 
     if let Some(matches) = matches.subcommand_matches("cheatday") {
-        healthtracker::log_sport(
-            false,
-            false,
-            None,
-            true,
-            Some(matches.value_of("date").unwrap().to_string()),
-        )
-        .unwrap();
+        log_or_edit(Activity::CheatDay, matches);
     }
 
-    if matches.subcommand_matches("analyze").is_some() {
-        healthtracker::analyze().unwrap();
+    if let Some(matches) = matches.subcommand_matches("strength") {
+        log_or_edit(
+            Activity::SetRep {
+                kind: matches.value_of("exercise").unwrap().to_string(),
+                sets: matches.value_of("sets").unwrap().parse().unwrap(),
+                reps: matches.value_of("reps").unwrap().parse().unwrap(),
+            },
+            matches,
+        );
+    }
+
+    for kind in TIME_DISTANCE_ACTIVITIES {
+        if let Some(matches) = matches.subcommand_matches(kind) {
+            log_or_edit(
+                Activity::TimeDistance {
+                    kind: kind.to_string(),
+                    duration: matches
+                        .value_of("duration")
+                        .map(|d| WorkoutDuration::parse(d).unwrap()),
+                    distance: Distance::parse(matches.value_of("distance").unwrap()).unwrap(),
+                },
+                matches,
+            );
+        }
+    }
+
+    if let Some(matches) = matches.subcommand_matches("delete") {
+        let id = RecordId::from_str(matches.value_of("id").unwrap()).unwrap();
+        healthtracker::delete_activity(id).unwrap();
+    }
+
+    if let Some(matches) = matches.subcommand_matches("analyze") {
+        healthtracker::analyze(Some(matches.value_of("period").unwrap().to_string())).unwrap();
+    }
+
+    if let Some(matches) = matches.subcommand_matches("export") {
+        let path = std::path::Path::new(matches.value_of("file").unwrap());
+        healthtracker::export_csv(path).unwrap();
+    }
+
+    if let Some(matches) = matches.subcommand_matches("import") {
+        if matches.is_present("legacy") {
+            healthtracker::import_csv_legacy(
+                matches.value_of("steps-file").map(std::path::Path::new),
+                matches.value_of("weight-file").map(std::path::Path::new),
+                matches.value_of("biking-file").map(std::path::Path::new),
+            )
+            .unwrap();
+        } else {
+            let path = std::path::Path::new(matches.value_of("file").unwrap());
+            healthtracker::import_csv(path).unwrap();
+        }
     }
 }
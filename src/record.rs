@@ -0,0 +1,58 @@
+//! The append-only log entries `History` is built from.
+
+use crate::activity::Activity;
+use crate::datetime::DateTimeTz;
+use crate::HealthTrackerError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// A record's stable identity, assigned once at creation and reused by
+/// later edits and deletes of the same record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RecordId(Uuid);
+
+impl RecordId {
+    pub fn new() -> Self {
+        Self(Uuid::new_v4())
+    }
+}
+
+impl Default for RecordId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for RecordId {
+    type Err = HealthTrackerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(s)
+            .map(Self)
+            .map_err(|e| HealthTrackerError::Dummy(format!("'{}' is not a record id: {}", s, e)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RecordBody {
+    /// a logged or edited activity
+    Entry(Activity),
+    /// marks a prior record for this id as deleted
+    Tombstone,
+}
+
+/// One line of the append-only log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    pub id: RecordId,
+    pub timestamp: DateTimeTz,
+    pub body: RecordBody,
+}
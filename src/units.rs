@@ -0,0 +1,286 @@
+//! Typed physical quantities for the values `healthtracker` tracks.
+//!
+//! Weight and distance are backed by [`dimensioned::si`] quantities so a
+//! kilogram can never be added to a kilometre by accident. The wrapper
+//! types here exist because `dimensioned` quantities don't implement
+//! `serde` themselves; serialization stores the magnitude together with
+//! its unit (e.g. `"75.5 kg"`) so the RON file stays self-describing.
+
+use crate::HealthTrackerError;
+use dimensioned::si::{Kilogram, Meter, KG, M};
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+const KG_PER_LB: f64 = 0.453_592;
+const KM_PER_MI: f64 = 1.609_34;
+
+/// A body weight, always held internally as kilograms.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Weight(Kilogram<f64>);
+
+impl Weight {
+    pub fn from_kg(value: f64) -> Self {
+        Self(value * KG)
+    }
+
+    pub fn kg(self) -> f64 {
+        self.0.value_unsafe
+    }
+
+    /// Parse a CLI value such as `75kg` or `180lb` into a [`Weight`].
+    /// A bare number is assumed to already be in kilograms.
+    pub fn parse(s: &str) -> Result<Self, HealthTrackerError> {
+        let s = s.trim();
+        if let Some(value) = s.strip_suffix("kg") {
+            Ok(Self::from_kg(parse_magnitude(value)?))
+        } else if let Some(value) = s.strip_suffix("lb") {
+            Ok(Self::from_kg(parse_magnitude(value)? * KG_PER_LB))
+        } else {
+            Ok(Self::from_kg(parse_magnitude(s)?))
+        }
+    }
+}
+
+/// A distance, always held internally as metres.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Distance(Meter<f64>);
+
+impl Distance {
+    pub fn from_m(value: f64) -> Self {
+        Self(value * M)
+    }
+
+    pub fn from_km(value: f64) -> Self {
+        Self::from_m(value * 1000.0)
+    }
+
+    pub fn km(self) -> f64 {
+        self.0.value_unsafe / 1000.0
+    }
+
+    /// Parse a CLI value such as `10km` or `6mi` into a [`Distance`].
+    /// A bare number is assumed to already be in kilometres.
+    pub fn parse(s: &str) -> Result<Self, HealthTrackerError> {
+        let s = s.trim();
+        if let Some(value) = s.strip_suffix("km") {
+            Ok(Self::from_km(parse_magnitude(value)?))
+        } else if let Some(value) = s.strip_suffix("mi") {
+            Ok(Self::from_km(parse_magnitude(value)? * KM_PER_MI))
+        } else {
+            Ok(Self::from_km(parse_magnitude(s)?))
+        }
+    }
+}
+
+fn parse_magnitude(s: &str) -> Result<f64, HealthTrackerError> {
+    s.trim()
+        .parse::<f64>()
+        .map_err(|e| HealthTrackerError::Dummy(format!("'{}' is not a number: {}", s, e)))
+}
+
+impl fmt::Display for Weight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} kg", self.kg())
+    }
+}
+
+impl fmt::Display for Distance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} km", self.km())
+    }
+}
+
+impl Serialize for Weight {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{} kg", self.kg()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(WeightVisitor)
+    }
+}
+
+struct WeightVisitor;
+
+impl<'de> Visitor<'de> for WeightVisitor {
+    type Value = Weight;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(r#"a weight like "75 kg", or a bare number from an older, unit-less file"#)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let v = v.trim();
+        let magnitude = v.strip_suffix("kg").unwrap_or(v);
+        magnitude
+            .trim()
+            .parse::<f64>()
+            .map(Weight::from_kg)
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Weight::from_kg(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Weight::from_kg(v as f64))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Weight::from_kg(v as f64))
+    }
+}
+
+impl Serialize for Distance {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{} km", self.km()))
+    }
+}
+
+impl<'de> Deserialize<'de> for Distance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(DistanceVisitor)
+    }
+}
+
+struct DistanceVisitor;
+
+impl<'de> Visitor<'de> for DistanceVisitor {
+    type Value = Distance;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(r#"a distance like "10 km", or a bare number from an older, unit-less file"#)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let v = v.trim();
+        let magnitude = v.strip_suffix("km").unwrap_or(v);
+        magnitude
+            .trim()
+            .parse::<f64>()
+            .map(Distance::from_km)
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Distance::from_km(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Distance::from_km(v as f64))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Distance::from_km(v as f64))
+    }
+}
+
+/// How long an activity took, held internally as whole minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WorkoutDuration(u32);
+
+impl WorkoutDuration {
+    pub fn from_minutes(minutes: u32) -> Self {
+        Self(minutes)
+    }
+
+    pub fn minutes(self) -> u32 {
+        self.0
+    }
+
+    /// Parse a CLI value such as `30min` or a bare `30` (assumed minutes).
+    pub fn parse(s: &str) -> Result<Self, HealthTrackerError> {
+        let s = s.trim();
+        let minutes = s.strip_suffix("min").unwrap_or(s);
+        minutes
+            .trim()
+            .parse::<u32>()
+            .map(Self::from_minutes)
+            .map_err(|e| HealthTrackerError::Dummy(format!("'{}' is not whole minutes: {}", s, e)))
+    }
+}
+
+impl fmt::Display for WorkoutDuration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} min", self.0)
+    }
+}
+
+impl Serialize for WorkoutDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{} min", self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for WorkoutDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(WorkoutDurationVisitor)
+    }
+}
+
+struct WorkoutDurationVisitor;
+
+impl<'de> Visitor<'de> for WorkoutDurationVisitor {
+    type Value = WorkoutDuration;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(r#"a duration like "30 min""#)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        let v = v.trim();
+        let minutes = v.strip_suffix("min").unwrap_or(v);
+        minutes
+            .trim()
+            .parse::<u32>()
+            .map(WorkoutDuration::from_minutes)
+            .map_err(de::Error::custom)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(WorkoutDuration::from_minutes(v as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weight_parses_kg_lb_and_bare_numbers() {
+        assert!((Weight::parse("75kg").unwrap().kg() - 75.0).abs() < f64::EPSILON);
+        assert!((Weight::parse("180lb").unwrap().kg() - 180.0 * KG_PER_LB).abs() < 1e-9);
+        assert!((Weight::parse("80").unwrap().kg() - 80.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn distance_parses_km_mi_and_bare_numbers() {
+        assert!((Distance::parse("10km").unwrap().km() - 10.0).abs() < f64::EPSILON);
+        assert!((Distance::parse("6mi").unwrap().km() - 6.0 * KM_PER_MI).abs() < 1e-9);
+        assert!((Distance::parse("5").unwrap().km() - 5.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn duration_parses_min_suffix_and_bare_numbers() {
+        assert_eq!(WorkoutDuration::parse("30min").unwrap().minutes(), 30);
+        assert_eq!(WorkoutDuration::parse("45").unwrap().minutes(), 45);
+    }
+
+    #[test]
+    fn weight_serde_round_trips_through_ron() {
+        let weight = Weight::from_kg(72.5);
+        let serialized = ron::ser::to_string(&weight).unwrap();
+        let deserialized: Weight = ron::from_str(&serialized).unwrap();
+        assert!((deserialized.kg() - 72.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn weight_deserializes_legacy_bare_number() {
+        let deserialized: Weight = ron::from_str("72.5").unwrap();
+        assert!((deserialized.kg() - 72.5).abs() < f64::EPSILON);
+    }
+}